@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::engine::{AnyEngine, OutputMode, SqlEngine};
+use crate::format::{self, PatchFormat};
+use crate::options::JdOptions;
+use crate::read_inputs;
+
+/// One `(file1, file2, expected_exit)` case in a `--manifest` file.
+#[derive(Debug, Deserialize)]
+struct ManifestCase {
+    file1: PathBuf,
+    file2: PathBuf,
+    expected_exit: i32,
+    // jd-style `-set`/`-mset`/`-precision`/`-setkeys` flags for this case.
+    #[serde(default)]
+    extra: Vec<String>,
+}
+
+/// Run every case in `manifest_path` against a single pooled connection,
+/// printing a TAP-style summary and returning a process exit code (nonzero
+/// if any case disagreed with its `expected_exit`).
+pub async fn run(
+    cfg: &Config,
+    manifest_path: &Path,
+    format: PatchFormat,
+    output: OutputMode,
+) -> Result<i32> {
+    let cases = load_manifest(manifest_path)?;
+    let backend = AnyEngine::connect(cfg).await?;
+
+    println!("1..{}", cases.len());
+    let mut failed = 0usize;
+    for (i, case) in cases.iter().enumerate() {
+        let n = i + 1;
+        let desc = format!("{} vs {}", case.file1.display(), case.file2.display());
+        match run_case(&backend, case, format, output).await {
+            Ok(actual_exit) if actual_exit == case.expected_exit => {
+                println!("ok {} - {}", n, desc);
+            }
+            Ok(actual_exit) => {
+                failed += 1;
+                println!(
+                    "not ok {} - {} (expected exit {}, got {})",
+                    n, desc, case.expected_exit, actual_exit
+                );
+            }
+            Err(err) => {
+                failed += 1;
+                println!("not ok {} - {} (error: {})", n, desc, err);
+            }
+        }
+    }
+    println!("# {} passed, {} failed", cases.len() - failed, failed);
+
+    Ok(if failed == 0 { 0 } else { 1 })
+}
+
+async fn run_case(
+    backend: &AnyEngine,
+    case: &ManifestCase,
+    format: PatchFormat,
+    output: OutputMode,
+) -> Result<i32> {
+    let (a, b) = read_inputs(&case.file1, &case.file2)?;
+    let options = JdOptions::parse(&case.extra)?.to_json();
+    let result = backend.eval(a, b, options, output).await?;
+    let (_, exit_code) = format::render(&result, format)?;
+    Ok(exit_code)
+}
+
+/// Load a manifest as JSON (a top-level array of `ManifestCase` objects) or
+/// as TSV (`file1\tfile2\texpected_exit` per line, `#`-prefixed lines and
+/// blank lines ignored).
+fn load_manifest(path: &Path) -> Result<Vec<ManifestCase>> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read manifest: {}", path.display()))?;
+
+    if raw.trim_start().starts_with('[') {
+        serde_json::from_str(&raw)
+            .with_context(|| format!("invalid JSON manifest: {}", path.display()))
+    } else {
+        parse_tsv_manifest(&raw)
+            .with_context(|| format!("invalid TSV manifest: {}", path.display()))
+    }
+}
+
+fn parse_tsv_manifest(raw: &str) -> Result<Vec<ManifestCase>> {
+    let mut cases = Vec::new();
+    for (i, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 3 {
+            return Err(anyhow!(
+                "line {}: expected 3 tab-separated fields (file1, file2, expected_exit), got {}",
+                i + 1,
+                fields.len()
+            ));
+        }
+        let expected_exit: i32 = fields[2]
+            .trim()
+            .parse()
+            .with_context(|| format!("line {}: invalid expected_exit '{}'", i + 1, fields[2]))?;
+        cases.push(ManifestCase {
+            file1: PathBuf::from(fields[0]),
+            file2: PathBuf::from(fields[1]),
+            expected_exit,
+            extra: Vec::new(),
+        });
+    }
+    Ok(cases)
+}
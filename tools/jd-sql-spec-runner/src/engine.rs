@@ -0,0 +1,311 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::{Map, Value as JsonValue};
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::options::references_param3;
+use crate::retry::{retry_connect, RetryPolicy};
+
+/// Which concrete database the `Any` pool is actually talking to.
+///
+/// `sqlx`'s `Any` driver dispatches on the DSN scheme at connect time; the
+/// configured `engine` name is parsed into this so that MySQL's `?`
+/// placeholder rewriting and the engine named in error messages can key off
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineKind {
+    Postgres,
+    Sqlite,
+    Mysql,
+}
+
+impl EngineKind {
+    pub fn parse(engine: &str) -> Result<Self> {
+        match engine {
+            "postgres" | "pg" => Ok(EngineKind::Postgres),
+            "sqlite" => Ok(EngineKind::Sqlite),
+            "mysql" => Ok(EngineKind::Mysql),
+            other => Err(anyhow!(
+                "unsupported engine '{}' (supported: postgres, sqlite, mysql)",
+                other
+            )),
+        }
+    }
+}
+
+pub enum QueryResult {
+    Text(String),
+    Json(JsonValue),
+    Rows(Vec<Map<String, JsonValue>>),
+    None,
+}
+
+/// How `eval` should shape its result.
+///
+/// `Single` keeps the original behaviour: sniff column 0 of row 0 as TEXT or
+/// a JSON scalar. `Rows` materializes every row of the result set as a JSON
+/// object keyed by column name, for debugging a SQL `jd` implementation by
+/// selecting intermediate columns (path, old, new, op) in one query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum OutputMode {
+    #[default]
+    Single,
+    Rows,
+}
+
+/// A SQL backend capable of evaluating a `jd`-in-SQL implementation.
+///
+/// Every engine is handed the same `Config.dsn`/`Config.sql`; the only thing
+/// that changes between Postgres, SQLite, and MySQL is the JSON dialect the
+/// user's SQL relies on (`jsonb`/`json_patch`, `json()`, `JSON_*`, ...). The
+/// parameter binding and TEXT-or-JSON result sniffing live here once, instead
+/// of being duplicated per engine.
+#[async_trait]
+pub trait SqlEngine: Sized {
+    async fn connect(cfg: &Config) -> Result<Self>;
+
+    async fn eval(
+        &self,
+        a: Option<JsonValue>,
+        b: Option<JsonValue>,
+        options: Option<JsonValue>,
+        output: OutputMode,
+    ) -> Result<QueryResult>;
+}
+
+/// `SqlEngine` implementation backed by `sqlx`'s `Any` driver.
+///
+/// This is the one engine we ship: the DSN scheme (`postgres://`,
+/// `sqlite://`, `mysql://`) tells `sqlx` which wire protocol to speak, and
+/// `kind` (parsed from `Config.engine`) selects MySQL's sequential `?`
+/// binding and names the engine in error messages.
+pub struct AnyEngine {
+    pool: sqlx::AnyPool,
+    kind: EngineKind,
+    sql: String,
+    // Precomputed once at connect time: does the user's SQL reference the
+    // `$3` options header, so we know whether to bind it at all.
+    wants_options: bool,
+    // Only populated for MySQL: `sql` has had every `$1`/`$2`/`$3` token
+    // rewritten to `?` (MySQL only understands sequential `?` placeholders,
+    // not Postgres-style numbered ones), and this records which original
+    // parameter number each `?` corresponds to, in left-to-right order, so
+    // binds can be issued in the matching sequence.
+    sequential_bind_order: Vec<u8>,
+}
+
+#[async_trait]
+impl SqlEngine for AnyEngine {
+    async fn connect(cfg: &Config) -> Result<Self> {
+        let kind = EngineKind::parse(&cfg.engine)?;
+        sqlx::any::install_default_drivers();
+
+        let policy = RetryPolicy {
+            max_elapsed: cfg
+                .connect_max_elapsed_secs
+                .map(Duration::from_secs)
+                .unwrap_or(RetryPolicy::default().max_elapsed),
+            ..RetryPolicy::default()
+        };
+
+        let pool = retry_connect(policy, || AnyPoolOptions::new().max_connections(1).connect(&cfg.dsn))
+            .await
+            .with_context(|| format!("failed to connect to {:?} at {}", kind, cfg.dsn))?;
+
+        let (sql, sequential_bind_order, wants_options) = if kind == EngineKind::Mysql {
+            let (rewritten, order) = rewrite_placeholders_for_sequential_binds(&cfg.sql);
+            let wants_options = order.contains(&3);
+            (rewritten, order, wants_options)
+        } else {
+            (cfg.sql.clone(), Vec::new(), references_param3(&cfg.sql))
+        };
+
+        Ok(AnyEngine {
+            pool,
+            kind,
+            sql,
+            wants_options,
+            sequential_bind_order,
+        })
+    }
+
+    async fn eval(
+        &self,
+        a: Option<JsonValue>,
+        b: Option<JsonValue>,
+        options: Option<JsonValue>,
+        output: OutputMode,
+    ) -> Result<QueryResult> {
+        // JSON values are bound as their serialized text; the user's SQL is
+        // expected to cast them (`$1::jsonb`, `CAST($1 AS JSON)`, ...) the
+        // way it already does for Postgres. None becomes a SQL NULL so the
+        // empty_to_value/value_to_empty jd spec cases keep working.
+        let a_text = a.as_ref().map(serde_json::to_string).transpose()?;
+        let b_text = b.as_ref().map(serde_json::to_string).transpose()?;
+        let options_text = options.as_ref().map(serde_json::to_string).transpose()?;
+
+        let mut query = sqlx::query(&self.sql);
+        if self.kind == EngineKind::Mysql {
+            // `self.sql` was already rewritten to sequential `?`s at connect
+            // time; bind in the order those `?`s actually appeared.
+            for param in &self.sequential_bind_order {
+                query = match param {
+                    1 => query.bind(a_text.clone()),
+                    2 => query.bind(b_text.clone()),
+                    3 => query.bind(options_text.clone()),
+                    _ => unreachable!("rewrite only emits params 1..=3"),
+                };
+            }
+        } else {
+            // Postgres/SQLite bind by explicit `$N` index, so the bind call
+            // order doesn't need to match where `$1`/`$2`/`$3` appear in the
+            // query text.
+            query = query.bind(a_text).bind(b_text);
+            if self.wants_options {
+                query = query.bind(options_text);
+            }
+        }
+
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| format!("SQL execution failed against {:?}", self.kind))?;
+
+        match output {
+            OutputMode::Rows => Ok(QueryResult::Rows(
+                rows.iter().map(row_to_json_object).collect::<Result<_>>()?,
+            )),
+            OutputMode::Single => {
+                let Some(row) = rows.into_iter().next() else {
+                    return Ok(QueryResult::None);
+                };
+                self.sniff_first_column(&row)
+            }
+        }
+    }
+}
+
+impl AnyEngine {
+    fn sniff_first_column(&self, row: &AnyRow) -> Result<QueryResult> {
+        let raw = row.try_get_raw(0).context("reading first column failed")?;
+        if raw.is_null() {
+            return Ok(QueryResult::None);
+        }
+
+        // A column the driver reports as JSON/JSONB is parsed as structured
+        // JSON; everything else is sniffed via `column_value_to_json`'s
+        // string-first order, same as `OutputMode::Rows` uses per column.
+        if column_is_json_typed(row, 0) {
+            let text = row
+                .try_get::<String, _>(0)
+                .context("reading json column as text failed")?;
+            let v: JsonValue = serde_json::from_str(&text)
+                .with_context(|| format!("invalid JSON in first column: {}", text))?;
+            return Ok(QueryResult::Json(v));
+        }
+
+        match column_value_to_json(row, 0)? {
+            JsonValue::String(s) => Ok(QueryResult::Text(s)),
+            v => Ok(QueryResult::Json(v)),
+        }
+    }
+}
+
+/// Whether the driver reports column `idx` as a JSON/JSONB type. Checked
+/// once via metadata (rather than racing type decodes) so a plain TEXT
+/// column whose *content* happens to look like a number or boolean isn't
+/// misclassified.
+fn column_is_json_typed(row: &AnyRow, idx: usize) -> bool {
+    row.columns()
+        .get(idx)
+        .map(|c| c.type_info().name().eq_ignore_ascii_case("json") || c.type_info().name().eq_ignore_ascii_case("jsonb"))
+        .unwrap_or(false)
+}
+
+/// Rewrite every `$1`/`$2`/`$3` token in `sql` into a sequential `?`
+/// placeholder (MySQL only understands `?`, not Postgres-style numbered
+/// params), and return the original parameter numbers in the order their
+/// tokens appeared, so binds can be issued in a matching sequence. A `$`
+/// followed by digits outside 1..=3 (e.g. `$10`, or a `$3` glued to more
+/// digits like `$30`) is left untouched, since it isn't one of ours.
+fn rewrite_placeholders_for_sequential_binds(sql: &str) -> (String, Vec<u8>) {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut order = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '$' && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+            let mut j = i + 1;
+            while chars.get(j).is_some_and(char::is_ascii_digit) {
+                j += 1;
+            }
+            let digits: String = chars[i + 1..j].iter().collect();
+            if let Ok(n @ 1..=3) = digits.parse::<u8>() {
+                out.push('?');
+                order.push(n);
+                i = j;
+                continue;
+            }
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    (out, order)
+}
+
+/// Materialize one row as a JSON object keyed by column name, for
+/// `OutputMode::Rows`.
+fn row_to_json_object(row: &AnyRow) -> Result<Map<String, JsonValue>> {
+    let mut obj = Map::with_capacity(row.columns().len());
+    for (idx, column) in row.columns().iter().enumerate() {
+        obj.insert(column.name().to_string(), column_value_to_json(row, idx)?);
+    }
+    Ok(obj)
+}
+
+/// Map a single column's value into `serde_json::Value`, covering the
+/// common types a `jd`-in-SQL implementation's debug columns tend to use:
+/// null, JSON/JSONB (parsed from its text form), text, bool, and numeric.
+///
+/// Text is tried before bool/numeric so a dynamically-typed column (e.g.
+/// SQLite, which has no real JSON column type and no strict column typing)
+/// doesn't get its TEXT content reinterpreted as a JSON scalar just because
+/// it happens to look like one. This is the same order `sniff_first_column`
+/// uses for `OutputMode::Single`, so a given column value sniffs the same
+/// way regardless of which output mode is active.
+fn column_value_to_json(row: &AnyRow, idx: usize) -> Result<JsonValue> {
+    let raw = row
+        .try_get_raw(idx)
+        .with_context(|| format!("reading column {} failed", idx))?;
+    if raw.is_null() {
+        return Ok(JsonValue::Null);
+    }
+
+    if column_is_json_typed(row, idx) {
+        let text = row
+            .try_get::<String, _>(idx)
+            .with_context(|| format!("reading json column {} as text failed", idx))?;
+        return serde_json::from_str(&text)
+            .with_context(|| format!("invalid JSON in column {}: {}", idx, text));
+    }
+    if let Ok(v) = row.try_get::<String, _>(idx) {
+        return Ok(JsonValue::String(v));
+    }
+    if let Ok(v) = row.try_get::<bool, _>(idx) {
+        return Ok(JsonValue::Bool(v));
+    }
+    if let Ok(v) = row.try_get::<i64, _>(idx) {
+        return Ok(JsonValue::Number(v.into()));
+    }
+    if let Ok(v) = row.try_get::<f64, _>(idx) {
+        return Ok(serde_json::Number::from_f64(v)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null));
+    }
+
+    Err(anyhow!("unsupported result type in column {}", idx))
+}
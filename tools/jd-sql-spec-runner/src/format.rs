@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::engine::QueryResult;
+
+/// Output format for a TEXT result containing `jd`'s native diff format.
+///
+/// `Jd` passes the native format through unchanged (the default, and the
+/// only thing the runner understood before this flag existed). `Patch` and
+/// `Merge` translate it into the corresponding IETF RFC.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[value(rename_all = "lower")]
+pub enum PatchFormat {
+    #[default]
+    Jd,
+    Patch,
+    Merge,
+}
+
+/// One hunk of `jd`'s native diff format:
+///   @ ["a","b"]
+///   - <removed JSON, if any>
+///   + <added JSON, if any>
+struct Hunk {
+    path: Vec<JsonValue>,
+    removed: Option<JsonValue>,
+    added: Option<JsonValue>,
+}
+
+/// Parse `jd`'s native hunk format into a list of `Hunk`s.
+fn parse_hunks(native: &str) -> Result<Vec<Hunk>> {
+    let mut hunks = Vec::new();
+    let mut lines = native.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let path_json = line
+            .strip_prefix("@ ")
+            .ok_or_else(|| anyhow!("expected hunk header ('@ [...]'), got: {}", line))?;
+        let path: Vec<JsonValue> = serde_json::from_str(path_json)
+            .with_context(|| format!("invalid hunk path: {}", path_json))?;
+
+        let mut removed = None;
+        let mut added = None;
+        while let Some(next) = lines.peek() {
+            if next.trim_end().starts_with("@ ") {
+                break;
+            }
+            let next = lines.next().unwrap().trim_end();
+            if next.is_empty() {
+                continue;
+            } else if let Some(v) = next.strip_prefix("- ") {
+                removed = Some(
+                    serde_json::from_str(v)
+                        .with_context(|| format!("invalid removed value: {}", v))?,
+                );
+            } else if let Some(v) = next.strip_prefix("+ ") {
+                added = Some(
+                    serde_json::from_str(v)
+                        .with_context(|| format!("invalid added value: {}", v))?,
+                );
+            } else {
+                return Err(anyhow!("unexpected line in jd diff output: {}", next));
+            }
+        }
+
+        hunks.push(Hunk {
+            path,
+            removed,
+            added,
+        });
+    }
+
+    Ok(hunks)
+}
+
+/// Encode a `jd` path (array of object keys / array indices) as a single
+/// RFC 6901 JSON Pointer, escaping `~`->`~0` and `/`->`~1` in each segment.
+fn path_to_pointer(path: &[JsonValue]) -> String {
+    let mut pointer = String::new();
+    for segment in path {
+        let raw = match segment {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        pointer.push('/');
+        pointer.push_str(&raw.replace('~', "~0").replace('/', "~1"));
+    }
+    pointer
+}
+
+/// Translate `jd`'s native diff format into an RFC 6902 JSON Patch document.
+pub fn to_json_patch(native: &str) -> Result<JsonValue> {
+    let hunks = parse_hunks(native)?;
+    let mut ops = Vec::with_capacity(hunks.len());
+    for hunk in &hunks {
+        let path = path_to_pointer(&hunk.path);
+        let op = match (&hunk.removed, &hunk.added) {
+            (Some(_), Some(added)) => json!({"op": "replace", "path": path, "value": added}),
+            (None, Some(added)) => json!({"op": "add", "path": path, "value": added}),
+            (Some(_), None) => json!({"op": "remove", "path": path}),
+            (None, None) => continue,
+        };
+        ops.push(op);
+    }
+    Ok(JsonValue::Array(ops))
+}
+
+/// Translate `jd`'s native diff format into an RFC 7386 JSON Merge Patch
+/// document: a single nested object, with each hunk's `+` value set at its
+/// path and a pure removal represented as `null` at the leaf.
+///
+/// Merge Patch (RFC 7386) has no way to address an array element by index —
+/// the only array operation it supports is wholesale replacement of the
+/// array itself, which a single hunk doesn't carry enough information to
+/// construct. So a hunk whose path touches an array index is rejected
+/// rather than silently reinterpreted as an object key.
+pub fn to_merge_patch(native: &str) -> Result<JsonValue> {
+    let hunks = parse_hunks(native)?;
+    let mut root = JsonValue::Object(Map::new());
+    for hunk in &hunks {
+        if let Some(index) = hunk.path.iter().find(|seg| seg.is_number()) {
+            return Err(anyhow!(
+                "cannot represent an array-index diff (path segment {}) as a JSON Merge Patch; use --format patch instead",
+                index
+            ));
+        }
+        let leaf = hunk.added.clone().unwrap_or(JsonValue::Null);
+        set_at_path(&mut root, &hunk.path, leaf);
+    }
+    Ok(root)
+}
+
+/// Render a `QueryResult` per the chosen `--format`, returning the text to
+/// print (if any, without a forced trailing newline) and the diff/no-diff
+/// exit code (0 => no diff, 1 => diff present).
+pub fn render(result: &QueryResult, format: PatchFormat) -> Result<(Option<String>, i32)> {
+    match result {
+        QueryResult::None => Ok((None, 0)),
+        QueryResult::Text(s) => match format {
+            PatchFormat::Jd => Ok((Some(s.clone()), !s.trim().is_empty() as i32)),
+            PatchFormat::Patch => {
+                let v = to_json_patch(s)?;
+                let exit_code = json_diff_present(&v) as i32;
+                Ok((Some(serde_json::to_string(&v)?), exit_code))
+            }
+            PatchFormat::Merge => {
+                let v = to_merge_patch(s)?;
+                let exit_code = json_diff_present(&v) as i32;
+                Ok((Some(serde_json::to_string(&v)?), exit_code))
+            }
+        },
+        QueryResult::Json(v) => {
+            let exit_code = json_diff_present(v) as i32;
+            Ok((Some(serde_json::to_string(v)?), exit_code))
+        }
+        QueryResult::Rows(rows) => {
+            let v = JsonValue::Array(rows.iter().cloned().map(JsonValue::Object).collect());
+            let exit_code = json_diff_present(&v) as i32;
+            Ok((Some(serde_json::to_string(&v)?), exit_code))
+        }
+    }
+}
+
+fn json_diff_present(v: &JsonValue) -> bool {
+    match v {
+        JsonValue::Null => false,
+        JsonValue::Bool(b) => *b, // unlikely result type; treat true as diff
+        JsonValue::Number(n) => n.as_i64().unwrap_or(0) != 0, // conservative
+        JsonValue::String(s) => !s.is_empty(),
+        JsonValue::Array(arr) => !arr.is_empty(),
+        JsonValue::Object(map) => !map.is_empty(),
+    }
+}
+
+fn set_at_path(node: &mut JsonValue, path: &[JsonValue], leaf: JsonValue) {
+    let Some(head) = path.first() else {
+        *node = leaf;
+        return;
+    };
+    let key = match head {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if !node.is_object() {
+        *node = JsonValue::Object(Map::new());
+    }
+    let map = node.as_object_mut().expect("just normalized to an object");
+    if path.len() == 1 {
+        map.insert(key, leaf);
+    } else {
+        let child = map.entry(key).or_insert_with(|| JsonValue::Object(Map::new()));
+        set_at_path(child, &path[1..], leaf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hunks_add_remove_replace() {
+        let native = "@ [\"a\"]\n+ 1\n@ [\"b\"]\n- 2\n@ [\"c\"]\n- 3\n+ 4\n";
+        let hunks = parse_hunks(native).unwrap();
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[0].added, Some(json!(1)));
+        assert_eq!(hunks[0].removed, None);
+        assert_eq!(hunks[1].removed, Some(json!(2)));
+        assert_eq!(hunks[1].added, None);
+        assert_eq!(hunks[2].removed, Some(json!(3)));
+        assert_eq!(hunks[2].added, Some(json!(4)));
+    }
+
+    #[test]
+    fn parse_hunks_rejects_missing_header() {
+        assert!(parse_hunks("+ 1\n").is_err());
+    }
+
+    #[test]
+    fn path_to_pointer_escapes_and_joins() {
+        assert_eq!(path_to_pointer(&[json!("a"), json!("b~c")]), "/a/b~0c");
+        assert_eq!(path_to_pointer(&[json!("a/b")]), "/a~1b");
+        assert_eq!(path_to_pointer(&[json!("items"), json!(2)]), "/items/2");
+    }
+
+    #[test]
+    fn json_patch_add_remove_replace() {
+        let native = "@ [\"a\"]\n+ 1\n@ [\"b\"]\n- 2\n@ [\"c\"]\n- 3\n+ 4\n";
+        let patch = to_json_patch(native).unwrap();
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "add", "path": "/a", "value": 1},
+                {"op": "remove", "path": "/b"},
+                {"op": "replace", "path": "/c", "value": 4},
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_patch_builds_nested_object() {
+        let native = "@ [\"a\",\"b\"]\n+ 1\n@ [\"c\"]\n- 2\n";
+        let patch = to_merge_patch(native).unwrap();
+        assert_eq!(patch, json!({"a": {"b": 1}, "c": null}));
+    }
+
+    #[test]
+    fn merge_patch_rejects_array_index_paths() {
+        let native = "@ [\"items\",0]\n+ 1\n";
+        assert!(to_merge_patch(native).is_err());
+    }
+}
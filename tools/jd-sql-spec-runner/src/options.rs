@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{Map, Value as JsonValue};
+
+/// `jd`-style comparison options, parsed out of the CLI's trailing `extra`
+/// args and bound to the user's SQL as the `$3` JSONB options header.
+///
+/// Mirrors a subset of upstream `jd`'s own flags so a SQL implementation can
+/// honor `-set`/`-mset`/`-precision`/`-setkeys` the same way the native
+/// `jd` CLI does.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JdOptions {
+    pub set: bool,
+    pub mset: bool,
+    pub precision: Option<f64>,
+    pub setkeys: Vec<String>,
+}
+
+impl JdOptions {
+    /// Parse `jd`-style flags out of the CLI's trailing args.
+    pub fn parse(extra: &[String]) -> Result<Self> {
+        let mut opts = JdOptions::default();
+        let mut iter = extra.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-set" => opts.set = true,
+                "-mset" => opts.mset = true,
+                "-precision" => {
+                    let raw = iter
+                        .next()
+                        .ok_or_else(|| anyhow!("-precision requires a value"))?;
+                    opts.precision = Some(
+                        raw.parse()
+                            .with_context(|| format!("invalid -precision value: {}", raw))?,
+                    );
+                }
+                s if s.starts_with("-precision=") => {
+                    let raw = &s["-precision=".len()..];
+                    opts.precision = Some(
+                        raw.parse()
+                            .with_context(|| format!("invalid -precision value: {}", raw))?,
+                    );
+                }
+                s if s.starts_with("-setkeys=") => {
+                    let raw = &s["-setkeys=".len()..];
+                    opts.setkeys = raw.split(',').map(str::to_string).collect();
+                }
+                _ => {
+                    // Unrecognized args (e.g. file paths picked up by the
+                    // positional fallback) are not ours to interpret.
+                }
+            }
+        }
+        Ok(opts)
+    }
+
+    /// Whether any option was actually set. When none was, `to_json` returns
+    /// `None` and `$3` (if the SQL references it) is passed as SQL NULL.
+    pub fn is_empty(&self) -> bool {
+        !self.set && !self.mset && self.precision.is_none() && self.setkeys.is_empty()
+    }
+
+    /// Render as the `{"set":true,"precision":0.01,"setkeys":["id"]}` header
+    /// object a SQL implementation reads via `$3->>'...'`.
+    pub fn to_json(&self) -> Option<JsonValue> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut map = Map::new();
+        if self.set {
+            map.insert("set".to_string(), JsonValue::Bool(true));
+        }
+        if self.mset {
+            map.insert("mset".to_string(), JsonValue::Bool(true));
+        }
+        if let Some(precision) = self.precision {
+            map.insert(
+                "precision".to_string(),
+                serde_json::Number::from_f64(precision)
+                    .map(JsonValue::Number)
+                    .unwrap_or(JsonValue::Null),
+            );
+        }
+        if !self.setkeys.is_empty() {
+            map.insert(
+                "setkeys".to_string(),
+                JsonValue::Array(self.setkeys.iter().cloned().map(JsonValue::String).collect()),
+            );
+        }
+        Some(JsonValue::Object(map))
+    }
+}
+
+/// Whether `sql` actually references the `$3` options header parameter, so
+/// we only bind three params when the user's query asks for them (existing
+/// 2-param queries must keep working unmodified).
+///
+/// Checks word boundaries (not preceded or followed by an ASCII digit) so
+/// `$30` or a `$3` glued to more digits isn't mistaken for our placeholder.
+pub fn references_param3(sql: &str) -> bool {
+    let bytes = sql.as_bytes();
+    let mut start = 0;
+    while let Some(pos) = sql[start..].find("$3") {
+        let idx = start + pos;
+        let before_ok = idx == 0 || !bytes[idx - 1].is_ascii_digit();
+        let after = idx + 2;
+        let after_ok = after >= bytes.len() || !bytes[after].is_ascii_digit();
+        if before_ok && after_ok {
+            return true;
+        }
+        start = idx + 2;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_param3_matches_bare_token() {
+        assert!(references_param3("SELECT jd($1, $2, $3)"));
+    }
+
+    #[test]
+    fn references_param3_ignores_longer_numbers() {
+        assert!(!references_param3("SELECT $30"));
+        assert!(!references_param3("SELECT $13"));
+    }
+
+    #[test]
+    fn references_param3_absent() {
+        assert!(!references_param3("SELECT jd($1, $2)"));
+    }
+
+    #[test]
+    fn parse_collects_flags_and_precision() {
+        let extra = vec![
+            "-set".to_string(),
+            "-precision".to_string(),
+            "0.01".to_string(),
+            "-setkeys=id,name".to_string(),
+        ];
+        let opts = JdOptions::parse(&extra).unwrap();
+        assert!(opts.set);
+        assert!(!opts.mset);
+        assert_eq!(opts.precision, Some(0.01));
+        assert_eq!(opts.setkeys, vec!["id".to_string(), "name".to_string()]);
+    }
+
+    #[test]
+    fn parse_empty_extra_is_empty() {
+        let opts = JdOptions::parse(&[]).unwrap();
+        assert!(opts.is_empty());
+        assert_eq!(opts.to_json(), None);
+    }
+
+    #[test]
+    fn precision_without_value_errors() {
+        let extra = vec!["-precision".to_string()];
+        assert!(JdOptions::parse(&extra).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::future::Future;
+use std::io;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+/// Backoff schedule for transient connection failures.
+///
+/// Defaults are tuned for "the DB container hasn't finished starting yet":
+/// start small, double each attempt, cap the individual sleep so a flaky run
+/// doesn't wait minutes between tries, and give up once the whole retry
+/// loop has run longer than `max_elapsed`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_elapsed: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            max_elapsed: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Retry `attempt` with exponential backoff as long as its error is
+/// transient (per [`is_transient`]) and the policy's `max_elapsed` budget
+/// hasn't run out. Permanent errors (bad DSN, auth failure, ...) are
+/// returned immediately on the first attempt.
+pub async fn retry_connect<F, Fut, T>(policy: RetryPolicy, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let start = Instant::now();
+    let mut backoff = policy.initial_backoff;
+
+    loop {
+        match attempt().await {
+            Ok(v) => return Ok(v),
+            Err(err) => {
+                if !is_transient(&err) {
+                    return Err(anyhow!(err));
+                }
+                if start.elapsed() >= policy.max_elapsed {
+                    return Err(anyhow!(err))
+                        .map_err(|e| e.context("giving up after max connect retry elapsed time"));
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+            }
+        }
+    }
+}
+
+/// Transient errors are ones worth retrying: the DB process hasn't started
+/// accepting connections yet, or reset/aborted the connection mid-handshake
+/// while it was coming up. Anything else (auth failure, bad DSN, a query
+/// error once connected) is permanent and should fail the run immediately.
+fn is_transient(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(io_err) => matches!(
+            io_err.kind(),
+            io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_refused_is_transient() {
+        let err = sqlx::Error::Io(io::Error::from(io::ErrorKind::ConnectionRefused));
+        assert!(is_transient(&err));
+    }
+
+    #[test]
+    fn connection_reset_and_aborted_are_transient() {
+        assert!(is_transient(&sqlx::Error::Io(io::Error::from(
+            io::ErrorKind::ConnectionReset
+        ))));
+        assert!(is_transient(&sqlx::Error::Io(io::Error::from(
+            io::ErrorKind::ConnectionAborted
+        ))));
+    }
+
+    #[test]
+    fn other_io_errors_are_permanent() {
+        let err = sqlx::Error::Io(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(!is_transient(&err));
+    }
+
+    #[test]
+    fn non_io_errors_are_permanent() {
+        assert!(!is_transient(&sqlx::Error::RowNotFound));
+    }
+
+    #[tokio::test]
+    async fn retry_connect_gives_up_on_permanent_error() {
+        let policy = RetryPolicy::default();
+        let result: Result<(), _> =
+            retry_connect(policy, || async { Err(sqlx::Error::RowNotFound) }).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn retry_connect_succeeds_after_transient_failures() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+            max_elapsed: Duration::from_secs(5),
+        };
+        let mut attempts = 0;
+        let result = retry_connect(policy, || {
+            attempts += 1;
+            async move {
+                if attempts < 3 {
+                    Err(sqlx::Error::Io(io::Error::from(io::ErrorKind::ConnectionRefused)))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+    }
+}